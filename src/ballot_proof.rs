@@ -0,0 +1,297 @@
+//! Fiat-Shamir OR-proof that a Paillier ciphertext encodes exactly one
+//! legal single-vote message, without revealing which one.
+//!
+//! The legal message set is `S = {1, 2^b, 2^(2b), ..., 2^((C-1)b)}`, i.e.
+//! a single `1` shifted into one of the `C` per-candidate slots of width
+//! `b = per_candidate_bits`. Proving `c` encodes `m_i` reduces to proving
+//! that `c * (1+n)^(-m_i) mod n^2` is an n-th power mod n^2 (the prover
+//! knows the root because it is the randomness `r` used at encryption
+//! time). The disjunction is realized by simulating every branch except
+//! the true one and tying them together with a single Fiat-Shamir
+//! challenge, following the usual Cramer-Damgaard-Schoenmakers OR
+//! construction.
+
+use std::ops::Mul;
+
+use crypto_bigint::{
+    CheckedAdd, Invert, RandomMod,
+    modular::{MontyForm, MontyParams},
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::UintType;
+
+/// One branch of the OR-proof: the commitment `a`, this branch's share
+/// `e` of the global challenge, and the response `z`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BallotProofBranch {
+    pub a: UintType,
+    pub e: u32,
+    pub z: UintType,
+}
+
+/// A full disjunctive proof that `c` encodes one of the legal messages,
+/// one branch per candidate slot.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BallotProof {
+    pub branches: Vec<BallotProofBranch>,
+}
+
+/// Builds `S = {1, 2^b, 2^(2b), ..., 2^((C-1)b)}`, the legal single-vote
+/// messages for `n_candidates` slots of `per_candidate_bits` bits each.
+pub fn legal_messages(per_candidate_bits: u32, n_candidates: usize) -> Vec<UintType> {
+    (0..n_candidates)
+        .map(|i| UintType::from_u8(1).shl(per_candidate_bits * i as u32))
+        .collect()
+}
+
+/// Fiat-Shamir challenge `H(n, c, a_0, ..., a_{C-1})`, truncated to 32 bits.
+fn fiat_shamir_challenge(n: &UintType, c: &UintType, commitments: &[UintType]) -> u32 {
+    let mut hasher = Sha256::new();
+    hasher.update(n.to_string_radix_vartime(10).as_bytes());
+    hasher.update(c.to_string_radix_vartime(10).as_bytes());
+    for a in commitments {
+        hasher.update(a.to_string_radix_vartime(10).as_bytes());
+    }
+    let digest = hasher.finalize();
+    u32::from_be_bytes(digest[0..4].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+/// Produces a proof that `c = (1+n)^m * r^n mod n^2` encodes
+/// `messages[true_index]`, given the randomness `r` used at encryption.
+pub fn prove_ballot(
+    n: &UintType,
+    c: &UintType,
+    r: &UintType,
+    true_index: usize,
+    messages: &[UintType],
+) -> BallotProof {
+    // Will be required for every branch below.
+    let n_squared = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width.");
+    let n_squared_odd = n_squared
+        .to_odd()
+        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
+    let n_squared_nz = n_squared.to_nz().expect("N^2 must not be 0.");
+    let monty_params_modulus_nsquare = MontyParams::new(n_squared_odd);
+
+    let one = UintType::ONE;
+    let n_plus_one: UintType = n
+        .checked_add(&one)
+        .expect("N+1 must not overflow in given datatype. Increase UintType.");
+    let n_plus_one = MontyForm::new(&n_plus_one, monty_params_modulus_nsquare);
+    let c_monty = MontyForm::new(c, monty_params_modulus_nsquare);
+    let r_monty = MontyForm::new(r, monty_params_modulus_nsquare);
+
+    // `target(m) = c * (1+n)^(-m) mod n^2`: an n-th power exactly when `c`
+    // truly encodes `m`.
+    let target = |m: &UintType| {
+        let inv = n_plus_one
+            .pow(m)
+            .invert()
+            .expect("(1+n)^m must be invertible mod n^2, since gcd(1+n, n^2) = 1.");
+        c_monty.mul(inv)
+    };
+
+    let mut as_: Vec<UintType> = Vec::with_capacity(messages.len());
+    let mut es: Vec<u32> = vec![0; messages.len()];
+    let mut zs: Vec<UintType> = vec![UintType::ZERO; messages.len()];
+    let mut simulated_e_sum: u32 = 0;
+    let mut u_for_true_branch: Option<MontyForm<{ UintType::LIMBS }>> = None;
+
+    for (i, m_i) in messages.iter().enumerate() {
+        if i == true_index {
+            // Honest commitment: pick u at random, a = u^n mod n^2.
+            let u = UintType::random_mod(&mut OsRng, &n_squared_nz);
+            let u_monty = MontyForm::new(&u, monty_params_modulus_nsquare);
+            as_.push(u_monty.pow(n).retrieve());
+            u_for_true_branch = Some(u_monty);
+            continue;
+        }
+
+        // Simulated branch: pick e_i, z_i at random and solve for a_i.
+        let e_i: u32 = OsRng.next_u32();
+        let z_i = UintType::random_mod(&mut OsRng, &n_squared_nz);
+        let z_i_monty = MontyForm::new(&z_i, monty_params_modulus_nsquare);
+
+        let target_i = target(m_i);
+        let target_pow_e = target_i.pow(&UintType::from_u32(e_i));
+        let a_i = z_i_monty
+            .pow(n)
+            .mul(target_pow_e.invert().expect("target(m_i) must be invertible mod n^2."))
+            .retrieve();
+
+        simulated_e_sum = simulated_e_sum.wrapping_add(e_i);
+        as_.push(a_i);
+        es[i] = e_i;
+        zs[i] = z_i;
+    }
+
+    let e = fiat_shamir_challenge(n, c, &as_);
+    let e_true = e.wrapping_sub(simulated_e_sum);
+
+    let u_monty = u_for_true_branch.expect("true branch commitment was set above");
+    let z_true = u_monty.mul(r_monty.pow(&UintType::from_u32(e_true))).retrieve();
+    es[true_index] = e_true;
+    zs[true_index] = z_true;
+
+    BallotProof {
+        branches: as_
+            .into_iter()
+            .zip(es)
+            .zip(zs)
+            .map(|((a, e), z)| BallotProofBranch { a, e, z })
+            .collect(),
+    }
+}
+
+/// Verifies a `BallotProof` against the legal message set, checking that
+/// every branch's relation holds and that the branch challenges sum to
+/// the recomputed Fiat-Shamir challenge.
+pub fn verify_ballot(n: &UintType, c: &UintType, proof: &BallotProof, messages: &[UintType]) -> bool {
+    if proof.branches.len() != messages.len() {
+        return false;
+    }
+
+    let n_squared: UintType = match Option::from(n.checked_square()) {
+        Some(v) => v,
+        None => return false,
+    };
+    let n_squared_odd = match Option::from(n_squared.to_odd()) {
+        Some(v) => v,
+        None => return false,
+    };
+    let monty_params_modulus_nsquare = MontyParams::new(n_squared_odd);
+
+    let one = UintType::ONE;
+    let n_plus_one: UintType = match n.checked_add(&one).into_option() {
+        Some(v) => v,
+        None => return false,
+    };
+    let n_plus_one = MontyForm::new(&n_plus_one, monty_params_modulus_nsquare);
+    let c_monty = MontyForm::new(c, monty_params_modulus_nsquare);
+
+    let target = |m: &UintType| {
+        let inv = n_plus_one
+            .pow(m)
+            .invert()
+            .expect("(1+n)^m must be invertible mod n^2, since gcd(1+n, n^2) = 1.");
+        c_monty.mul(inv)
+    };
+
+    let commitments: Vec<UintType> = proof.branches.iter().map(|b| b.a).collect();
+    let e = fiat_shamir_challenge(n, c, &commitments);
+
+    let mut e_sum: u32 = 0;
+    for (branch, m_i) in proof.branches.iter().zip(messages.iter()) {
+        e_sum = e_sum.wrapping_add(branch.e);
+
+        let target_i = target(m_i);
+        let lhs = MontyForm::new(&branch.z, monty_params_modulus_nsquare)
+            .pow(n)
+            .retrieve();
+        let rhs = MontyForm::new(&branch.a, monty_params_modulus_nsquare)
+            .mul(target_i.pow(&UintType::from_u32(branch.e)))
+            .retrieve();
+
+        if lhs != rhs {
+            return false;
+        }
+    }
+
+    e_sum == e
+}
+
+impl BallotProof {
+    /// Ad-hoc decimal encoding: branches separated by `;`, fields within
+    /// a branch separated by `,`, in `a,e,z` order.
+    pub fn encode(&self) -> String {
+        self.branches
+            .iter()
+            .map(|b| {
+                format!(
+                    "{},{},{}",
+                    b.a.to_string_radix_vartime(10),
+                    b.e,
+                    b.z.to_string_radix_vartime(10)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Inverse of [`BallotProof::encode`].
+    pub fn decode(s: &str) -> Self {
+        let branches = s
+            .trim()
+            .split(';')
+            .map(|branch| {
+                let fields: Vec<&str> = branch.split(',').collect();
+                assert!(fields.len() == 3, "Each proof branch must be \"a,e,z\".");
+                let a = UintType::from_str_radix_vartime(fields[0], 10)
+                    .expect("a must be a valid Uint. Check if in word size bounds.");
+                let e: u32 = fields[1].parse().expect("e must be a valid u32.");
+                let z = UintType::from_str_radix_vartime(fields[2], 10)
+                    .expect("z must be a valid Uint. Check if in word size bounds.");
+                BallotProofBranch { a, e, z }
+            })
+            .collect();
+        BallotProof { branches }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paillier::{self, Plaintext};
+
+    const N: u32 = 143; // p=11, q=13
+    const PER_CANDIDATE_BITS: u32 = 2;
+    const N_CANDIDATES: usize = 2;
+
+    fn n() -> UintType {
+        UintType::from_u32(N)
+    }
+
+    #[test]
+    fn valid_ballot_proof_verifies() {
+        let n = n();
+        let messages = legal_messages(PER_CANDIDATE_BITS, N_CANDIDATES);
+        let r = UintType::from_u32(2);
+        let true_index = 1;
+        let c = paillier::encrypt(&n, &Plaintext(messages[true_index]), &r).0;
+
+        let proof = prove_ballot(&n, &c, &r, true_index, &messages);
+        assert!(verify_ballot(&n, &c, &proof, &messages));
+    }
+
+    #[test]
+    fn tampered_ciphertext_rejected() {
+        let n = n();
+        let messages = legal_messages(PER_CANDIDATE_BITS, N_CANDIDATES);
+        let r = UintType::from_u32(2);
+        let true_index = 0;
+        let c = paillier::encrypt(&n, &Plaintext(messages[true_index]), &r).0;
+
+        let proof = prove_ballot(&n, &c, &r, true_index, &messages);
+        let tampered_c = c.checked_add(&UintType::ONE).expect("c+1 must not overflow.");
+        assert!(!verify_ballot(&n, &tampered_c, &proof, &messages));
+    }
+
+    #[test]
+    fn ciphertext_for_illegal_message_rejected() {
+        let n = n();
+        let messages = legal_messages(PER_CANDIDATE_BITS, N_CANDIDATES);
+        let r = UintType::from_u32(2);
+        // 3 is not in the legal set {1, 4}: encoding it and then claiming
+        // (dishonestly) that it's messages[0] = 1 must fail verification.
+        let illegal_message = UintType::from_u32(3);
+        let c = paillier::encrypt(&n, &Plaintext(illegal_message), &r).0;
+
+        let proof = prove_ballot(&n, &c, &r, 0, &messages);
+        assert!(!verify_ballot(&n, &c, &proof, &messages));
+    }
+}
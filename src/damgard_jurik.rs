@@ -0,0 +1,187 @@
+//! Damgård–Jurik: a generalization of the Paillier scheme used elsewhere
+//! in this file, lifting the plaintext space from `Z_n` to `Z_{n^s}` for a
+//! chosen expansion parameter `s` (`s = 1` is plain Paillier). Encryption
+//! works mod `n^(s+1)` instead of `n^2`, so a single small `n` can carry
+//! far more candidates/voters than `Z_n` alone without widening the RSA
+//! modulus itself.
+//!
+//! Encryption mirrors the `s = 1` case: `c = (1+n)^m * r^(n^s) mod
+//! n^(s+1)`. Decryption raises `c` to the secret `phi_n` to land on
+//! `(1+n)^(phi_n*m) mod n^(s+1)` (the `r^(n^s)` blinding vanishes exactly
+//! as it does for plain Paillier), then recovers `phi_n*m mod n^s` one
+//! base-`n` digit at a time via the recursive discrete-log algorithm: at
+//! step `j` the digits of `m` already known (mod `n^(j-1)`) are enough to
+//! compute the binomial-expansion contribution of every term up to
+//! `C(m,j) n^(j-1)`, so subtracting it off isolates the next digit.
+//! Multiplying by `phi_n^(-1) mod n^s` finishes the same division
+//! [`crate::decrypt`] does mod `n`.
+
+use crypto_bigint::{
+    CheckedAdd, CheckedMul, CheckedSub,
+    modular::{MontyForm, MontyParams},
+};
+
+use crate::UintType;
+
+fn n_pow(n: &UintType, exponent: usize) -> UintType {
+    let mut result = UintType::ONE;
+    for _ in 0..exponent {
+        result = result
+            .checked_mul(n)
+            .expect("n^exponent must not overflow in the given datatype. Increase UintType.");
+    }
+    result
+}
+
+fn monty_params(modulus: &UintType) -> MontyParams<{ UintType::LIMBS }> {
+    let modulus_odd = modulus
+        .to_odd()
+        .expect("n^(s+1) must be odd, because p is odd and q is odd. odd*odd->odd, odd^j=odd.");
+    MontyParams::new(modulus_odd)
+}
+
+/// `c = (1+n)^m * r^(n^s) mod n^(s+1)`, the Damgård–Jurik generalization
+/// of Paillier encryption. `m` must be smaller than `n^s`.
+pub fn encrypt(n: &UintType, s: usize, m: &UintType, r: &UintType) -> UintType {
+    let params = monty_params(&n_pow(n, s + 1));
+    let n_plus_one = n
+        .checked_add(&UintType::ONE)
+        .expect("n+1 must not overflow in the given datatype. Increase UintType.");
+    let n_to_s = n_pow(n, s);
+
+    let message_term = MontyForm::new(&n_plus_one, params).pow(m);
+    let blinding_term = MontyForm::new(r, params).pow(&n_to_s);
+    message_term.mul(&blinding_term).retrieve()
+}
+
+/// `binomial(x, k) mod modulus`, i.e. `C(x,k) = x*(x-1)*...*(x-k+1) / k!`
+/// reduced mod `modulus`. `x` is only ever known mod `modulus` here (it's
+/// `decode`'s partial digit accumulator), so every `x-i` term is a
+/// *modular* subtraction -- computed as `(x+modulus)-i` so it never
+/// underflows, even when `x < i` as an integer (e.g. `x = 0`, which
+/// happens whenever the digit of `m` decoded so far is 0). The division
+/// by `k!` likewise has to be a modular inverse rather than an exact
+/// integer division, since only `x mod modulus` is available.
+fn binomial_mod(x: &UintType, k: usize, modulus: &UintType) -> UintType {
+    let modulus_nz = modulus.to_nz().expect("modulus must not be 0.");
+    let x = x.rem(&modulus_nz);
+
+    let mut numerator = UintType::ONE;
+    for i in 0..k {
+        let i_uint = UintType::from_u32(i as u32);
+        let term = x
+            .checked_add(modulus)
+            .expect("x+modulus must not overflow. Increase UintType.")
+            .checked_sub(&i_uint)
+            .expect("x+modulus-i must not underflow; i <= k <= modulus.")
+            .rem(&modulus_nz);
+        numerator = numerator.mul_mod(&term, &modulus_nz);
+    }
+    let mut factorial_k = UintType::ONE;
+    for i in 1..=k {
+        factorial_k = factorial_k
+            .checked_mul(&UintType::from_u32(i as u32))
+            .expect("k! must not overflow. Increase UintType.");
+    }
+    let factorial_k_inv = factorial_k
+        .inv_mod(modulus)
+        .expect("k! must be invertible mod n^j (pick n_candidates/n_voters so k! is coprime to n).");
+    numerator.mul_mod(&factorial_k_inv, &modulus_nz)
+}
+
+/// Recovers `m` from `a = (1+n)^m mod n^(s+1)`, one base-`n` digit of `m`
+/// at a time: at step `j`, `L_j(a mod n^(j+1)) = m + sum_{k=2}^{j}
+/// C(m,k) n^(k-1) mod n^j`, and every `C(m,k)` term only needs `m mod
+/// n^(j-k+1)` -- already known from the previous step -- to be exact mod
+/// `n^j`, so subtracting it off isolates `m mod n^j`.
+fn decode(a: &UintType, n: &UintType, s: usize) -> UintType {
+    let n_nz = n.to_nz().expect("n must not be 0.");
+    let mut m = UintType::ZERO;
+
+    for j in 1..=s {
+        let n_to_j = n_pow(n, j);
+        let n_to_j_nz = n_to_j.to_nz().expect("n^j must not be 0.");
+        let n_to_j_plus_1_nz = n_pow(n, j + 1).to_nz().expect("n^(j+1) must not be 0.");
+
+        let a_j_minus_one = a
+            .rem(&n_to_j_plus_1_nz)
+            .checked_sub(&UintType::ONE)
+            .expect("a mod n^(j+1) - 1 must not underflow; a must be congruent to 1 mod n.");
+        let (t_j, _) = a_j_minus_one.div_rem(&n_nz);
+        let t_j = t_j.rem(&n_to_j_nz);
+
+        let mut correction = UintType::ZERO;
+        for k in 2..=j {
+            let n_pow_k_minus_1 = n_pow(n, k - 1).rem(&n_to_j_nz);
+            let term = binomial_mod(&m, k, &n_to_j).mul_mod(&n_pow_k_minus_1, &n_to_j_nz);
+            correction = correction
+                .checked_add(&term)
+                .expect("Correction sum must not overflow. Increase UintType.")
+                .rem(&n_to_j_nz);
+        }
+
+        m = t_j
+            .checked_add(&n_to_j)
+            .expect("t_j+n^j must not overflow. Increase UintType.")
+            .checked_sub(&correction)
+            .expect("t_j+n^j-correction must not underflow; correction must be smaller than n^j.")
+            .rem(&n_to_j_nz);
+    }
+
+    m
+}
+
+/// Decrypts `ciphertext` under expansion parameter `s`, given the
+/// authority's secret `phi_n`.
+pub fn decrypt(n: &UintType, s: usize, phi_n: &UintType, ciphertext: &UintType) -> UintType {
+    let params = monty_params(&n_pow(n, s + 1));
+    let a = MontyForm::new(ciphertext, params).pow(phi_n).retrieve();
+    let scaled_m = decode(&a, n, s);
+
+    let n_to_s = n_pow(n, s);
+    let n_to_s_nz = n_to_s.to_nz().expect("n^s must not be 0.");
+    let phi_n_inv = phi_n
+        .inv_mod(&n_to_s)
+        .expect("Phi(N) must have a multiplicative inverse mod n^s.");
+
+    scaled_m.mul_mod(&phi_n_inv, &n_to_s_nz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P: u32 = 11;
+    const Q: u32 = 13;
+    const S: usize = 2;
+
+    fn n_and_phi_n() -> (UintType, UintType) {
+        let n = UintType::from_u32(P * Q);
+        let phi_n = UintType::from_u32((P - 1) * (Q - 1));
+        (n, phi_n)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (n, phi_n) = n_and_phi_n();
+        let m = UintType::from_u32(1000); // within Z_{n^S} = Z_{143^2}
+        let r = UintType::from_u32(2);
+
+        let c = encrypt(&n, S, &m, &r);
+        let recovered = decrypt(&n, S, &phi_n, &c);
+        assert_eq!(recovered, m);
+    }
+
+    #[test]
+    fn round_trip_for_message_congruent_to_zero_mod_n() {
+        // m = n drives the first decoded digit to 0, which used to panic
+        // binomial's raw integer subtraction (0 - 1 underflows).
+        let (n, phi_n) = n_and_phi_n();
+        let m = n;
+        let r = UintType::from_u32(2);
+
+        let c = encrypt(&n, S, &m, &r);
+        let recovered = decrypt(&n, S, &phi_n, &c);
+        assert_eq!(recovered, m);
+    }
+}
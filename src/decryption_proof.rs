@@ -0,0 +1,239 @@
+//! Verifiable-decryption proof.
+//!
+//! `decrypt` currently hands back a plaintext that nobody but the
+//! authority can check, because computing it consumes the secret `phi_n`.
+//! This module lets the authority instead publish, alongside each `m`, a
+//! non-interactive proof that `m` really is what `phi_n` decrypts the
+//! ciphertext to -- checkable by anyone who knows only the public modulus
+//! `n`, the fixed public base `s`, the one-time setup commitment
+//! `h = s^phi_n mod n^2`, and `phi_n_inv = phi_n^(-1) mod n` (published by
+//! the authority alongside `h`, since without it nobody but the authority
+//! could finish the division `decrypt` does).
+//!
+//! The proof is a Chaum-Pedersen style equality-of-exponents sigma
+//! protocol: the same secret `phi_n` is used as the exponent in both `h`
+//! (relative to base `s`) and `d = c^phi_n mod n^2` (relative to base
+//! `c`), and Fiat-Shamir makes it non-interactive. Because both `h` and
+//! `d` are built by raising an element of `Z*_n2` to `phi_n`, `L(h)` and
+//! `L(d)` are both clean multiples of `phi_n` (the standard Paillier fact
+//! that any `x^phi_n mod n^2` is `1 mod n`), so the sigma protocol alone
+//! is enough to convince a verifier that `d` used the same `phi_n` as
+//! `h` -- `phi_n_inv` is only needed to turn `L(d)` into `m`, exactly as
+//! `decrypt` turns `d1` into `m` with the secret `phi_n` itself.
+
+use std::ops::Mul;
+
+use crypto_bigint::{
+    CheckedAdd, CheckedMul, CheckedSub, RandomBits,
+    modular::{MontyForm, MontyParams},
+};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::UintType;
+use crate::paillier::{AuthorityKeypair, AuthorityPublicKey};
+
+/// Extra bits of statistical hiding given to `rho` beyond what `phi_n`
+/// and the challenge `e` need, so that `z = rho + e*phi_n` reveals
+/// nothing about `phi_n` (see [`rho_bit_length`]).
+const KAPPA: u32 = 48;
+
+/// `rho` must be sampled from a range exponentially larger than `e*phi_n`
+/// -- this is a sigma protocol over the unknown-order group `Z*_n2`, not
+/// one mod a known group order, so `rho` can't just be reduced mod `n^2`
+/// the way `h` and `d` are. Undersizing it (e.g. to `n^2`, `phi_n`'s own
+/// modulus) lets `floor(z/e)` recover `phi_n` exactly once `e*phi_n`
+/// dwarfs `rho`'s range. Panics rather than silently truncating the
+/// margin if `UintType` is too narrow to fit it for the given `n`.
+fn rho_bit_length(n: &UintType) -> u32 {
+    let phi_n_bits = 2 * n.bits();
+    let challenge_bits = u32::BITS;
+    let bit_length = phi_n_bits + challenge_bits + KAPPA;
+    assert!(
+        bit_length <= UintType::BITS - 8,
+        "n is too wide for UintType to give rho a safe statistical-hiding margin; widen UintType."
+    );
+    bit_length
+}
+
+/// Non-interactive proof that `d = c^phi_n mod n^2` was computed with the
+/// same `phi_n` that is committed to in the public `h`.
+#[derive(Serialize, Deserialize)]
+pub struct DecryptionProof {
+    /// `d = c^phi_n mod n^2`, published so the verifier can re-derive `m`.
+    pub d: UintType,
+    /// Announcement `a = s^rho mod n^2`, mirroring how `h` is built.
+    pub a: UintType,
+    /// Announcement `b = c^rho mod n^2`, mirroring how `d` is built.
+    pub b: UintType,
+    pub e: u32,
+    pub z: UintType,
+}
+
+fn monty_params(n: &UintType) -> MontyParams<{ UintType::LIMBS }> {
+    let n_squared = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width.");
+    let n_squared_odd = n_squared
+        .to_odd()
+        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
+    MontyParams::new(n_squared_odd)
+}
+
+/// `h = s^phi_n mod n^2`, the one-time setup commitment an authority
+/// publishes alongside its public key and fixed base `s`.
+pub fn compute_commitment(n: &UintType, phi_n: &UintType, s: &UintType) -> UintType {
+    let params = monty_params(n);
+    MontyForm::new(s, params).pow(phi_n).retrieve()
+}
+
+fn fiat_shamir_challenge(n: &UintType, s: &UintType, h: &UintType, c: &UintType, d: &UintType, a: &UintType, b: &UintType) -> u32 {
+    let mut hasher = Sha256::new();
+    for value in [n, s, h, c, d, a, b] {
+        hasher.update(value.to_string_radix_vartime(10).as_bytes());
+    }
+    let digest = hasher.finalize();
+    u32::from_be_bytes(digest[0..4].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+/// Builds a [`DecryptionProof`] that `c^phi_n mod n^2` used the same
+/// `phi_n` that is committed to in `keypair.public_key.commitment`.
+pub fn prove_decryption(keypair: &AuthorityKeypair<UintType>, c: &UintType) -> DecryptionProof {
+    let n = keypair.public_key.n;
+    let s = keypair.public_key.commitment_base;
+    let h = keypair.public_key.commitment;
+    let phi_n = keypair.private_key.phi_n;
+
+    let params = monty_params(&n);
+
+    let d = MontyForm::new(c, params).pow(&phi_n).retrieve();
+
+    // rho is deliberately NOT reduced mod n^2 like h and d are: it must
+    // statistically swamp e*phi_n (see rho_bit_length), and Z*_n2's order
+    // is secret anyway, so there is no smaller range to reduce it into.
+    let rho = UintType::random_bits(&mut OsRng, rho_bit_length(&n));
+    let a = MontyForm::new(&s, params).pow(&rho).retrieve();
+    let b = MontyForm::new(c, params).pow(&rho).retrieve();
+
+    let e = fiat_shamir_challenge(&n, &s, &h, c, &d, &a, &b);
+    let e_uint = UintType::from_u32(e);
+
+    let z = rho
+        .checked_add(
+            &phi_n
+                .checked_mul(&e_uint)
+                .expect("e*phi_n must not overflow. Increase UintType."),
+        )
+        .expect("rho+e*phi_n must not overflow. Increase UintType.");
+
+    DecryptionProof { d, a, b, e, z }
+}
+
+/// Verifies a [`DecryptionProof`] and confirms it is consistent with
+/// `claimed_m`, using only `public_key`'s public fields.
+pub fn verify_decryption(
+    public_key: &AuthorityPublicKey<UintType>,
+    c: &UintType,
+    claimed_m: &UintType,
+    proof: &DecryptionProof,
+) -> bool {
+    let n = &public_key.n;
+    let s = &public_key.commitment_base;
+    let h = &public_key.commitment;
+    let params = monty_params(n);
+
+    let e = fiat_shamir_challenge(n, s, h, c, &proof.d, &proof.a, &proof.b);
+    if e != proof.e {
+        return false;
+    }
+    let e_uint = UintType::from_u32(e);
+
+    let lhs_commitment = MontyForm::new(s, params).pow(&proof.z).retrieve();
+    let rhs_commitment = MontyForm::new(&proof.a, params)
+        .mul(MontyForm::new(h, params).pow(&e_uint))
+        .retrieve();
+    if lhs_commitment != rhs_commitment {
+        return false;
+    }
+
+    let lhs_decryption = MontyForm::new(c, params).pow(&proof.z).retrieve();
+    let rhs_decryption = MontyForm::new(&proof.b, params)
+        .mul(MontyForm::new(&proof.d, params).pow(&e_uint))
+        .retrieve();
+    if lhs_decryption != rhs_decryption {
+        return false;
+    }
+
+    // d was built as c^phi_n, so L(d) = phi_n*m mod n exactly (the usual
+    // Paillier L-function identity); phi_n_inv finishes the division
+    // `decrypt` does with the secret phi_n itself.
+    let n_nonzero = match Option::from(n.to_nz()) {
+        Some(v) => v,
+        None => return false,
+    };
+    let l_d = {
+        let d_minus_one = match proof.d.checked_sub(&UintType::ONE).into_option() {
+            Some(v) => v,
+            None => return false,
+        };
+        let (quotient, _) = d_minus_one.div_rem(&n_nonzero);
+        quotient.rem(&n_nonzero)
+    };
+
+    &l_d.mul_mod(&public_key.phi_n_inv, &n_nonzero) == claimed_m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paillier::{self, AuthorityKeypair, AuthorityPrivateKey, Plaintext};
+
+    const P: u32 = 11;
+    const Q: u32 = 13;
+
+    fn keypair() -> AuthorityKeypair<UintType> {
+        let n = UintType::from_u32(P * Q);
+        let private_key = AuthorityPrivateKey::from_primes(UintType::from_u32(P), UintType::from_u32(Q));
+        AuthorityKeypair::new(private_key, n)
+    }
+
+    #[test]
+    fn valid_decryption_proof_verifies() {
+        let keypair = keypair();
+        let n = keypair.public_key.n;
+        let m = Plaintext(UintType::from_u32(7));
+        let r = UintType::from_u32(2);
+        let c = paillier::encrypt(&n, &m, &r);
+
+        let (plaintext, proof) = keypair.decrypt_with_proof(&c);
+        assert_eq!(plaintext.0, m.0);
+        assert!(verify_decryption(&keypair.public_key, &c.0, &plaintext.0, &proof));
+    }
+
+    #[test]
+    fn proof_rejected_for_wrong_claimed_message() {
+        let keypair = keypair();
+        let n = keypair.public_key.n;
+        let m = Plaintext(UintType::from_u32(7));
+        let r = UintType::from_u32(2);
+        let c = paillier::encrypt(&n, &m, &r);
+
+        let (_, proof) = keypair.decrypt_with_proof(&c);
+        let wrong_m = UintType::from_u32(8);
+        assert!(!verify_decryption(&keypair.public_key, &c.0, &wrong_m, &proof));
+    }
+
+    #[test]
+    fn tampered_proof_rejected() {
+        let keypair = keypair();
+        let n = keypair.public_key.n;
+        let m = Plaintext(UintType::from_u32(7));
+        let r = UintType::from_u32(2);
+        let c = paillier::encrypt(&n, &m, &r);
+
+        let (plaintext, mut proof) = keypair.decrypt_with_proof(&c);
+        proof.z = proof.z.checked_add(&UintType::ONE).expect("z+1 must not overflow.");
+        assert!(!verify_decryption(&keypair.public_key, &c.0, &plaintext.0, &proof));
+    }
+}
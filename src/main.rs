@@ -1,55 +1,17 @@
-use std::{io::Write, ops::Mul};
+use std::io::Write;
 
-use crypto_bigint::{
-    CheckedAdd, CheckedMul, CheckedSub, Constants, Integer, RandomBits, RandomMod, U128,
-    modular::{MontyForm, MontyParams},
-};
-use crypto_primes::{generate_prime, generate_safe_prime};
+use crypto_bigint::{CheckedAdd, U128};
+use crypto_primes::generate_safe_prime;
 
-struct AuthorityPrivateKey<T> {
-    pub phi_n: T,
-}
+mod ballot_proof;
+mod damgard_jurik;
+mod decryption_proof;
+mod eligibility;
+mod paillier;
+mod threshold;
+mod wire;
 
-impl<T: Integer + RandomBits + RandomMod + Constants> AuthorityPrivateKey<T> {
-    pub fn get_phi_n(p: &T, q: &T) -> T {
-        let one = T::ONE;
-        let p_minus_one = p.checked_sub(&one).expect("p-1 must not underflow.");
-        let q_minus_one = q.checked_sub(&one).expect("q-1 must not underflow.");
-        p_minus_one.checked_mul(&q_minus_one).expect(
-            "(p-1)*(q-1) must not overflow. Check if the word size is sufficient to accomodate N ...",
-        )
-    }
-    #[allow(dead_code)]
-    pub fn new(bit_length: usize) -> Self {
-        let p = generate_prime(bit_length as u32);
-        let q = generate_prime(bit_length as u32);
-
-        Self::from_primes(p, q)
-    }
-    pub fn from_primes(p: T, q: T) -> Self {
-        let phi_n = Self::get_phi_n(&p, &q);
-
-        AuthorityPrivateKey { phi_n }
-    }
-    pub fn from_phi_n(phi_n: T) -> Self {
-        AuthorityPrivateKey { phi_n }
-    }
-}
-
-struct AuthorityPublicKey<T> {
-    pub n: T,
-}
-
-impl<T: CheckedMul> AuthorityPublicKey<T> {
-    pub fn new(n: T) -> Self {
-        AuthorityPublicKey { n }
-    }
-}
-
-struct AuthorityKeypair<T> {
-    pub private_key: AuthorityPrivateKey<T>,
-    pub public_key: AuthorityPublicKey<T>,
-}
+use paillier::{AuthorityKeypair, AuthorityPrivateKey, Ciphertext, Plaintext};
 
 type UintType = U128; // Sufficient where the problem only asks for 28 bits.
 static N_VOTERS: usize = 16; // log2(N_VOTERS) determines the amount of bits needed to encode the number of voters per candidate
@@ -59,6 +21,22 @@ static NUM_BITS_WORKING: usize = 14;
 
 static N: UintType = UintType::from_u32(14351); // The public key of the authority. Must be known before the program runs.
 
+static THRESHOLD_K: usize = 5; // Number of authorities the decryption capability is shared across.
+static THRESHOLD_T: usize = 3; // Minimum number of authorities required to jointly decrypt.
+
+// The scheme above packs votes into Z_n (the `n_squared`-pinned proofs and
+// threshold machinery all assume `s = 1`), so N_VOTERS/N_CANDIDATES are
+// stuck under N's ~14 bits. damgard_jurik::encrypt/decrypt lift that
+// ceiling by working mod n^(s+1), with a Z_{n^s} plaintext space, for the
+// same small N -- no wider modulus needed.
+static DJ_S: usize = 2; // Plaintext space becomes Z_{n^DJ_S}, i.e. ~28 bits here.
+static DJ_N_VOTERS: usize = 256; // Would not fit alongside N_CANDIDATES in Z_n above.
+static DJ_N_CANDIDATES: usize = 3;
+
+static ELECTION_ID: &str = "datasec_evoting-demo-2026";
+static SIG_GROUP_BITS: u32 = 32; // Bit length of the eligibility-signature group's prime p.
+static ENROLLED_VOTERS: usize = 5; // Size of the simulated enrolled-voter set.
+
 fn main() {
     // These belong to the central authority...
     // let private_key: RsaPrivateKey<UintType> = RsaPrivateKey::new(14); // Working with 28 bits...
@@ -78,37 +56,46 @@ fn main() {
         vote_message = UintType::from_u8(1).shl(vote_shift);
     }
 
-    // Will be required in both voting (enc) and verification (dec) routines.
-    let n_squared = n
-        .checked_square()
-        .expect("N^2 must not overflow in the given datatype. Increase width."); // Not exactly true :)
-    let n_squared_odd = n_squared
-        .to_odd()
-        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
-
-    let vote_ciphertext;
-    {
-        let one = UintType::ONE;
-        let n_plus_one: UintType = n
-            .checked_add(&one)
-            .expect("N+1 must not overflow in given datatype. Increase UintType.");
-
-        // Monty-form enables modular exponentiation at low-cost.
-        // A Monty form must be initialized for a given integer with an odd modulus before computation.
-        let monty_params_modulus_nsquare = MontyParams::new(n_squared_odd);
-        let n_plus_one = MontyForm::new(&n_plus_one, monty_params_modulus_nsquare.clone());
-        let n_plus_one_pow_m = n_plus_one.pow(&vote_message);
-
-        let r_mod_nsquare = MontyForm::new(&r, monty_params_modulus_nsquare);
-        let r_pow_n = r_mod_nsquare.pow(&n);
-
-        let result = n_plus_one_pow_m.mul(r_pow_n);
-        let result_int = result.retrieve();
-
-        vote_ciphertext = result_int;
-    }
-
-    println!("ci = {}", vote_ciphertext.to_string_radix_vartime(10));
+    let vote_ciphertext = paillier::encrypt(&n, &Plaintext(vote_message), &r);
+
+    println!("ci = {}", vote_ciphertext.0.to_string_radix_vartime(10));
+
+    let legal_messages = ballot_proof::legal_messages(per_candidate_bits, N_CANDIDATES);
+    let true_index = (CHOSEN_CANDIDATE_IDX) as usize;
+    let validity_proof =
+        ballot_proof::prove_ballot(&n, &vote_ciphertext.0, &r, true_index, &legal_messages);
+    println!("proof = {}", validity_proof.encode());
+
+    // Enroll a simulated voter set and have the first one sign the
+    // ballot above, so submitting it later requires proving both that
+    // it encodes a legal vote (validity_proof) and that it came from a
+    // registered voter (eligibility_signature).
+    let sig_group = eligibility::SignatureGroup::new(SIG_GROUP_BITS);
+    let voters: Vec<eligibility::VoterKeypair> = (0..ENROLLED_VOTERS)
+        .map(|_| eligibility::VoterKeypair::new(&sig_group))
+        .collect();
+    let enrolled = voters.iter().map(|voter| voter.public_key).collect();
+    let mut registry = eligibility::EligibilityRegistry::new(sig_group, ELECTION_ID.to_string(), enrolled);
+
+    let eligibility_signature =
+        eligibility::sign_ballot(&registry.group, &voters[0], ELECTION_ID, &n, &vote_ciphertext.0);
+    println!("signature = {}", eligibility_signature.encode());
+
+    // The same submission bundled as one serde-serializable value -- what
+    // another program would produce instead of typing the three lines
+    // below into this demo's stdin loop.
+    let submitted_ballot = wire::SubmittedBallot {
+        ciphertext: vote_ciphertext,
+        validity_proof: validity_proof.clone(),
+        eligibility_signature: eligibility_signature.clone(),
+    };
+    let submitted_ballot_binary = wire::to_binary(&submitted_ballot);
+    println!("ballot (wire, text) = {}", wire::to_text(&submitted_ballot));
+    println!(
+        "ballot (wire, binary, {} bytes) = {:02x?}",
+        submitted_ballot_binary.len(),
+        submitted_ballot_binary
+    );
 
     println!("End of voting stage. Begin verification stage");
     println!("(p,q) or phi(n)? Input either p,q (separated by comma) or phi(N) without a comma.");
@@ -136,19 +123,19 @@ fn main() {
     };
     let phi_n = authority_priv.phi_n;
 
-    let authority_pub = AuthorityPublicKey::new(n);
-    let authority_keypair = AuthorityKeypair {
-        private_key: authority_priv,
-        public_key: authority_pub,
-    };
+    let authority_keypair = AuthorityKeypair::new(authority_priv, n);
+    println!(
+        "h = {} (published decryption-proof commitment)",
+        authority_keypair.public_key.commitment.to_string_radix_vartime(10)
+    );
 
     println!("phi(n) = {}", phi_n.to_string_radix_vartime(10));
 
     eprintln!(
-        "Enter the ciphertexts you would like to verify the contents of, input \"x\" to escape, \"pop\" to undo last:"
+        "Enter the ciphertexts you would like to verify the contents of, input \"x\" to escape, \"pop\" to undo last. Each ciphertext is followed by its validity proof, then its eligibility signature, each on its own line:"
     );
 
-    let mut vote_stack: Vec<UintType> = Vec::with_capacity(N_VOTERS);
+    let mut vote_stack: Vec<Ciphertext> = Vec::with_capacity(N_VOTERS);
     for i in 1.. {
         print!("{i}. ");
         std::io::stdout().flush().expect("Failure to flush stdout.");
@@ -162,7 +149,7 @@ fn main() {
             break;
         } else if input == "pop" {
             if let Some(vote) = vote_stack.pop() {
-                println!("Removed c={vote}.");
+                println!("Removed c={}.", vote.0);
             } else {
                 println!("Nothing to be done.");
             }
@@ -171,29 +158,91 @@ fn main() {
 
         let c = UintType::from_str_radix_vartime(&input, 10)
             .expect("Input ciphertext must be a valid Uint. Check if the word size is sufficient.");
+
+        print!("   proof. ");
+        std::io::stdout().flush().expect("Failure to flush stdout.");
+        let mut proof_input = String::with_capacity(256);
+        std::io::stdin()
+            .read_line(&mut proof_input)
+            .expect("Failure to use stdin");
+        let proof = ballot_proof::BallotProof::decode(&proof_input);
+
+        if !ballot_proof::verify_ballot(&n, &c, &proof, &legal_messages) {
+            println!("Ballot {i} failed its validity proof. Rejecting.");
+            continue;
+        }
+
+        print!("   signature. ");
+        std::io::stdout().flush().expect("Failure to flush stdout.");
+        let mut signature_input = String::with_capacity(128);
+        std::io::stdin()
+            .read_line(&mut signature_input)
+            .expect("Failure to use stdin");
+        let signature = eligibility::EligibilitySignature::decode(&signature_input);
+        let ballot = eligibility::Ballot {
+            ciphertext: c,
+            signature,
+        };
+        if !registry.verify_and_record(&n, &ballot) {
+            println!("Ballot {i} is not from a registered, not-yet-voted key. Rejecting.");
+            continue;
+        }
+        let c = Ciphertext(c);
         vote_stack.push(c);
 
-        let m = decrypt(&c, &authority_keypair);
-        let m1_small = m.as_words().first().expect("m must be non-empty");
+        let (m, decryption_proof) = authority_keypair.decrypt_with_proof(&c);
+        assert!(
+            decryption_proof::verify_decryption(&authority_keypair.public_key, &c.0, &m.0, &decryption_proof),
+            "Authority produced an invalid decryption proof for ballot {i}."
+        );
+        let m1_small = m.0.as_words().first().expect("m must be non-empty");
         println!("m{i} = {m} ({m:b})", m = m1_small);
     }
 
-    let n_squared_nz = n_squared.to_nz().expect("N-squared/N must not be 0");
     let product = vote_stack
         .iter()
-        .fold(UintType::ONE, |acc, vote| acc.mul_mod(vote, &n_squared_nz));
-    println!("prod(c) = {}", product.to_string_radix_vartime(10));
+        .fold(Ciphertext(UintType::ONE), |acc, vote| paillier::add(&n, &acc, vote));
+    println!("prod(c) = {}", product.0.to_string_radix_vartime(10));
 
-    let m_total = decrypt(&product, &authority_keypair);
+    let (m_total, tally_proof) = authority_keypair.decrypt_with_proof(&product);
+    assert!(
+        decryption_proof::verify_decryption(&authority_keypair.public_key, &product.0, &m_total.0, &tally_proof),
+        "Authority produced an invalid decryption proof for the final tally."
+    );
     let m_total_small = m_total
+        .0
         .as_words()
         .first()
         .expect("m_total must be non-empty");
     println!("m_final = {m} ({m:b})", m = m_total_small);
 
+    // Demonstrate that the tally never actually required any single
+    // authority to hold phi(n): a t-of-k committee sharing phi(n) via
+    // threshold::split recovers the exact same plaintext by combining
+    // partial decryptions instead.
+    let threshold_setup = threshold::split(&n, &phi_n, THRESHOLD_T, THRESHOLD_K);
+    let partials: Vec<threshold::PartialDecryption> = (1..=THRESHOLD_T)
+        .map(|i| threshold::partial_decrypt(&n, &product.0, &threshold_setup, i))
+        .collect();
+    for partial in &partials {
+        assert!(
+            threshold::verify_partial_decryption(&n, &product.0, &threshold_setup, partial),
+            "Authority {} produced an invalid partial decryption proof.",
+            partial.index
+        );
+    }
+    let m_total_threshold = threshold::combine(&n, &threshold_setup, &partials);
+    assert!(
+        m_total_threshold.eq(&m_total.0),
+        "A {THRESHOLD_T}-of-{THRESHOLD_K} committee must recover the same tally a single authority would."
+    );
+    println!(
+        "m_final (recovered by a {THRESHOLD_T}-of-{THRESHOLD_K} committee, no single authority holding phi(n)) = {m_total_threshold}"
+    );
+
     let m_total_small_upcasted = UintType::from_u64(*m_total_small);
     assert!(
-        m_total.eq(&m_total_small_upcasted),
+        m_total.0.eq(&m_total_small_upcasted),
         "Overflow when downcasting total votes to u64. Increase width..."
     );
 
@@ -207,7 +256,10 @@ fn main() {
         println!("Candidate {i}: {votes_for_candidate} votes.");
     }
 
-    let n_voters = vote_stack.len();
+    // registry.accepted_count(), not vote_stack.len(): "pop" can discard
+    // an accepted ballot from the stack without un-voting its signer, so
+    // the registry is the authoritative count of who actually voted.
+    let n_voters = registry.accepted_count();
     let votes = votes as usize;
     if votes > n_voters {
         let surplus = votes - n_voters;
@@ -218,46 +270,36 @@ fn main() {
         let deficit = n_voters - votes;
         println!("Deficit of {deficit} votes.");
     }
-}
-
-fn decrypt(ciphertext: &UintType, authority_keypair: &AuthorityKeypair<UintType>) -> UintType {
-    let n = authority_keypair.public_key.n;
-    let phi_n = authority_keypair.private_key.phi_n;
-
-    let n_squared = n
-        .checked_square()
-        .expect("N^2 must not overflow in the given datatype. Increase width."); // Not exactly true :)
-    let n_squared_odd = n_squared
-        .to_odd()
-        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
-
-    let d1 = {
-        // Modulus N^2 for this block
-        let monty_param_modulus_n_square = MontyParams::new(n_squared_odd);
-        let c_mod_n_square = MontyForm::new(&ciphertext, monty_param_modulus_n_square);
-
-        let c_pow_phi_n_mod_n_square = c_mod_n_square.pow(&phi_n);
-        c_pow_phi_n_mod_n_square.retrieve()
-    };
 
-    let n_nonzero = n.to_nz().expect("N must not be 0.");
-
-    let d2 = {
-        // Modulus N
-        let d1_minus_one = d1
-            .checked_sub(&UintType::ONE)
-            .expect("d1-1 must not underflow, d1 must not be 0.");
-
-        let (quotient, _) = d1_minus_one.div_rem(&n_nonzero);
-        quotient.rem(&n_nonzero)
-    };
+    // Demonstrate the Damgård–Jurik backend lifting the Z_n ceiling above:
+    // pack a tally spanning DJ_N_VOTERS/DJ_N_CANDIDATES, which would not
+    // fit in Z_n, into a Z_{n^DJ_S} plaintext instead.
+    let dj_per_candidate_bits = DJ_N_VOTERS.ilog2();
+    let dj_votes = [5u32, 9, 3];
+    assert!(dj_votes.len() == DJ_N_CANDIDATES);
+
+    let mut dj_message = UintType::ZERO;
+    for (i, &candidate_votes) in dj_votes.iter().enumerate() {
+        let term = UintType::from_u32(candidate_votes).shl(dj_per_candidate_bits * i as u32);
+        dj_message = dj_message
+            .checked_add(&term)
+            .expect("Packed Damgård–Jurik plaintext must not overflow.");
+    }
 
-    let d3 = {
-        // Modulus N
-        phi_n
-            .inv_mod(&n)
-            .expect("Phi(N) must have a multiplicative inverse.")
-    };
+    let dj_r: UintType = generate_safe_prime(NUM_BITS_WORKING as u32);
+    let dj_ciphertext = damgard_jurik::encrypt(&n, DJ_S, &dj_message, &dj_r);
+    let dj_recovered = damgard_jurik::decrypt(&n, DJ_S, &phi_n, &dj_ciphertext);
+    assert!(
+        dj_recovered.eq(&dj_message),
+        "Damgård–Jurik decryption must recover the packed plaintext."
+    );
 
-    d2.mul_mod(&d3, &n_nonzero)
+    let dj_voters_nz = UintType::from_u32(DJ_N_VOTERS as u32)
+        .to_nz()
+        .expect("DJ_N_VOTERS must not be 0.");
+    for i in 0..DJ_N_CANDIDATES {
+        let shifted = dj_recovered.shr(dj_per_candidate_bits * i as u32);
+        let votes_for_candidate = shifted.rem(&dj_voters_nz);
+        println!("Candidate {i} (Damgård–Jurik, s={DJ_S}): {votes_for_candidate} votes.");
+    }
 }
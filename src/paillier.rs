@@ -0,0 +1,204 @@
+//! Paillier primitives as a reusable library, rather than inline code in
+//! `main`. `encrypt`/`decrypt`/`add` are the three operations every other
+//! module in this crate actually needs: `main` only shifts a `1` into a
+//! ballot and calls `encrypt`, [`decryption_proof`] and [`threshold`] only
+//! need `decrypt`'s semantics (not its code) to build proofs around it,
+//! and the tally is nothing but repeated [`add`].
+//!
+//! `Ciphertext`/`Plaintext` are thin wrappers around `UintType` rather
+//! than bare `UintType`s passed positionally, so a caller can't
+//! accidentally feed a plaintext where a ciphertext is expected.
+
+use std::ops::Mul;
+
+use crypto_bigint::{
+    CheckedAdd, CheckedMul, CheckedSub, Constants, Integer, RandomBits, RandomMod,
+    modular::{MontyForm, MontyParams},
+};
+use crypto_primes::generate_prime;
+use serde::{Deserialize, Serialize};
+
+use crate::UintType;
+use crate::decryption_proof;
+
+pub struct AuthorityPrivateKey<T> {
+    pub phi_n: T,
+}
+
+impl<T: Integer + RandomBits + RandomMod + Constants> AuthorityPrivateKey<T> {
+    pub fn get_phi_n(p: &T, q: &T) -> T {
+        let one = T::ONE;
+        let p_minus_one = p.checked_sub(&one).expect("p-1 must not underflow.");
+        let q_minus_one = q.checked_sub(&one).expect("q-1 must not underflow.");
+        p_minus_one.checked_mul(&q_minus_one).expect(
+            "(p-1)*(q-1) must not overflow. Check if the word size is sufficient to accomodate N ...",
+        )
+    }
+    #[allow(dead_code)]
+    pub fn new(bit_length: usize) -> Self {
+        let p = generate_prime(bit_length as u32);
+        let q = generate_prime(bit_length as u32);
+
+        Self::from_primes(p, q)
+    }
+    pub fn from_primes(p: T, q: T) -> Self {
+        let phi_n = Self::get_phi_n(&p, &q);
+
+        Self::from_phi_n(phi_n)
+    }
+    pub fn from_phi_n(phi_n: T) -> Self {
+        AuthorityPrivateKey { phi_n }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AuthorityPublicKey<T> {
+    pub n: T,
+    /// Fixed public base `h = commitment_base^phi_n` is built from.
+    pub commitment_base: T,
+    /// One-time setup commitment `h = commitment_base^phi_n mod n^2`,
+    /// published so that [`decryption_proof::verify_decryption`] can
+    /// audit decryptions against the same `phi_n`.
+    pub commitment: T,
+    /// `phi_n^(-1) mod n`, published once by the authority alongside
+    /// `commitment` so a verifier can finish turning a proven `d = c^phi_n`
+    /// into `m`, the same division [`decrypt`] does with the secret `phi_n`.
+    pub phi_n_inv: T,
+}
+
+impl<T: CheckedMul> AuthorityPublicKey<T> {
+    pub fn new(n: T, commitment_base: T, commitment: T, phi_n_inv: T) -> Self {
+        AuthorityPublicKey {
+            n,
+            commitment_base,
+            commitment,
+            phi_n_inv,
+        }
+    }
+}
+
+pub struct AuthorityKeypair<T> {
+    pub private_key: AuthorityPrivateKey<T>,
+    pub public_key: AuthorityPublicKey<T>,
+}
+
+impl AuthorityKeypair<UintType> {
+    /// Finishes setup: samples the public base `commitment_base` modulo
+    /// `n^2` (only possible once `n` is known), derives the public
+    /// commitment `h = commitment_base^phi_n mod n^2`, and publishes
+    /// `phi_n^(-1) mod n` so verifiers can audit decryptions without the
+    /// authority handing out `phi_n` itself.
+    pub fn new(private_key: AuthorityPrivateKey<UintType>, n: UintType) -> Self {
+        let n_squared_nz = n
+            .checked_square()
+            .expect("N^2 must not overflow in the given datatype. Increase width.")
+            .to_nz()
+            .expect("N^2 must not be 0.");
+        let commitment_base = UintType::random_mod(&mut rand_core::OsRng, &n_squared_nz);
+
+        let commitment = decryption_proof::compute_commitment(&n, &private_key.phi_n, &commitment_base);
+        let phi_n_inv = private_key
+            .phi_n
+            .inv_mod(&n)
+            .expect("Phi(N) must have a multiplicative inverse.");
+
+        AuthorityKeypair {
+            private_key,
+            public_key: AuthorityPublicKey::new(n, commitment_base, commitment, phi_n_inv),
+        }
+    }
+
+    /// Decrypts `ciphertext` and produces a [`decryption_proof::DecryptionProof`]
+    /// that the reported plaintext really is what `phi_n` decrypts it to.
+    pub fn decrypt_with_proof(
+        &self,
+        ciphertext: &Ciphertext,
+    ) -> (Plaintext, decryption_proof::DecryptionProof) {
+        let plaintext = decrypt(self, ciphertext);
+        let proof = decryption_proof::prove_decryption(self, &ciphertext.0);
+        (plaintext, proof)
+    }
+}
+
+/// A Paillier ciphertext, i.e. an element of `Z*_n2`.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ciphertext(pub UintType);
+
+/// A Paillier plaintext, i.e. an element of `Z_n`.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plaintext(pub UintType);
+
+fn monty_params_n_squared(n: &UintType) -> MontyParams<{ UintType::LIMBS }> {
+    let n_squared = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width."); // Not exactly true :)
+    let n_squared_odd = n_squared
+        .to_odd()
+        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
+    MontyParams::new(n_squared_odd)
+}
+
+/// `c = (1+n)^m * r^n mod n^2`, plain Paillier encryption under public
+/// modulus `n` and randomness `r`. Only the modulus is needed here -- the
+/// rest of [`AuthorityPublicKey`] is decryption-proof bookkeeping that a
+/// voter encrypting a ballot has no use for.
+pub fn encrypt(n: &UintType, m: &Plaintext, r: &UintType) -> Ciphertext {
+    let n = *n;
+    let params = monty_params_n_squared(&n);
+
+    let one = UintType::ONE;
+    let n_plus_one: UintType = n
+        .checked_add(&one)
+        .expect("N+1 must not overflow in given datatype. Increase UintType.");
+
+    // Monty-form enables modular exponentiation at low-cost.
+    // A Monty form must be initialized for a given integer with an odd modulus before computation.
+    let n_plus_one = MontyForm::new(&n_plus_one, params);
+    let n_plus_one_pow_m = n_plus_one.pow(&m.0);
+
+    let r_mod_nsquare = MontyForm::new(r, params);
+    let r_pow_n = r_mod_nsquare.pow(&n);
+
+    Ciphertext(n_plus_one_pow_m.mul(r_pow_n).retrieve())
+}
+
+/// Decrypts `ciphertext` under `keypair`'s secret `phi_n`.
+pub fn decrypt(keypair: &AuthorityKeypair<UintType>, ciphertext: &Ciphertext) -> Plaintext {
+    let n = keypair.public_key.n;
+    let phi_n = keypair.private_key.phi_n;
+    let params = monty_params_n_squared(&n);
+
+    let d1 = {
+        let c_mod_n_square = MontyForm::new(&ciphertext.0, params);
+        let c_pow_phi_n_mod_n_square = c_mod_n_square.pow(&phi_n);
+        c_pow_phi_n_mod_n_square.retrieve()
+    };
+
+    let n_nonzero = n.to_nz().expect("N must not be 0.");
+
+    let d2 = {
+        let d1_minus_one = d1
+            .checked_sub(&UintType::ONE)
+            .expect("d1-1 must not underflow, d1 must not be 0.");
+
+        let (quotient, _) = d1_minus_one.div_rem(&n_nonzero);
+        quotient.rem(&n_nonzero)
+    };
+
+    let d3 = phi_n
+        .inv_mod(&n)
+        .expect("Phi(N) must have a multiplicative inverse.");
+
+    Plaintext(d2.mul_mod(&d3, &n_nonzero))
+}
+
+/// Homomorphic addition: `add(n, E(m1), E(m2))` decrypts to `m1+m2 mod
+/// n`, since Paillier ciphertexts combine by multiplying mod `n^2`.
+pub fn add(n: &UintType, c1: &Ciphertext, c2: &Ciphertext) -> Ciphertext {
+    let n_squared_nz = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width.")
+        .to_nz()
+        .expect("N^2 must not be 0.");
+    Ciphertext(c1.0.mul_mod(&c2.0, &n_squared_nz))
+}
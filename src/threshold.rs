@@ -0,0 +1,424 @@
+//! Threshold / distributed authority decryption.
+//!
+//! A single `AuthorityPrivateKey` holding all of `phi_n` can decrypt any
+//! individual ballot on its own, which means ballot secrecy rests
+//! entirely on that one authority's good behaviour. This module
+//! Shamir-shares `phi_n` across `k` authorities so that no fewer than `t`
+//! of them can jointly decrypt anything.
+//!
+//! Mirrors the usual Shoup-style threshold Paillier construction: the
+//! sharing polynomial's coefficients (beyond the constant term `phi_n`)
+//! are random, and reconstruction uses the *integer* Lagrange
+//! coefficients `delta * prod(j / (j - i))`, scaled by `delta = k!` so
+//! the division needed to build them is always exact -- no authority,
+//! and no combiner, ever divides by anything secret.
+//!
+//! Each authority publishes a partial decryption
+//! `c_i = c^(2*delta*lambda_i) mod n^2` together with a sigma proof that
+//! the same `lambda_i` committed to in its public verification key
+//! `v_i = v^lambda_i mod n^2` was used. [`combine`] takes any `t` valid
+//! partials and recovers the same plaintext a single authority holding
+//! all of `phi_n` would have decrypted to.
+
+use std::ops::Mul;
+
+use crypto_bigint::{
+    CheckedAdd, CheckedMul, CheckedSub, Invert, RandomBits, RandomMod,
+    modular::{MontyForm, MontyParams},
+};
+use rand_core::OsRng;
+use sha2::{Digest, Sha256};
+
+use crate::UintType;
+
+/// Extra bits of statistical hiding given to `rho` beyond what `lambda_i`
+/// and the challenge `e` need, so that `z = rho + e*lambda_i` reveals
+/// nothing about the share `lambda_i` (see [`rho_bit_length`]).
+const KAPPA: u32 = 48;
+
+/// `rho` must be sampled from a range exponentially larger than
+/// `e*lambda_i` -- this is a sigma protocol over the unknown-order group
+/// `Z*_n2`, so `rho` can't just be reduced mod `n^2` the way
+/// `verification_base` and `c^(2*delta)` are. Undersizing it lets
+/// `floor(z/e)` recover `lambda_i` exactly once `e*lambda_i` dwarfs
+/// `rho`'s range. Shares can be as large as `n^2` itself (the sharing
+/// polynomial's coefficients are sampled mod `n^2`), so `rho` is sized
+/// off `n^2`, not `n`. Panics rather than silently truncating the
+/// margin if `UintType` is too narrow to fit it for the given `n`.
+fn rho_bit_length(n: &UintType) -> u32 {
+    let lambda_i_bits = 2 * n.bits();
+    let challenge_bits = u32::BITS;
+    let bit_length = lambda_i_bits + challenge_bits + KAPPA;
+    assert!(
+        bit_length <= UintType::BITS - 8,
+        "n is too wide for UintType to give rho a safe statistical-hiding margin; widen UintType."
+    );
+    bit_length
+}
+
+/// Everything a dealer produces once, while it still holds `phi_n`, and
+/// then never needs again.
+pub struct ThresholdSetup {
+    pub t: usize,
+    pub k: usize,
+    /// `delta = k!`, the scaling factor that keeps every Lagrange
+    /// coefficient used during reconstruction an integer.
+    pub delta: UintType,
+    /// `lambda_i` for authority `i`, 1-indexed (`shares[0]` is authority 1's).
+    pub shares: Vec<UintType>,
+    /// Fixed public base used for verification keys.
+    pub verification_base: UintType,
+    /// `v_i = verification_base^lambda_i mod n^2`, 1-indexed like `shares`.
+    pub verification_keys: Vec<UintType>,
+    /// `(4 * delta^2 * phi_n)^(-1) mod n`, computed once by the dealer and
+    /// published alongside the verification keys. No authority needs
+    /// `phi_n` itself after this.
+    pub combination_inverse: UintType,
+}
+
+/// A single authority's contribution toward decrypting `c`.
+pub struct PartialDecryption {
+    /// 1-indexed authority index, matching `ThresholdSetup::shares`.
+    pub index: usize,
+    /// `c_i = c^(2*delta*lambda_i) mod n^2`.
+    pub value: UintType,
+    pub proof: PartialDecryptionProof,
+}
+
+/// Chaum-Pedersen style proof that `value` and `verification_keys[index]`
+/// were built by raising `verification_base` and `c^(2*delta)` to the
+/// same `lambda_i`.
+pub struct PartialDecryptionProof {
+    pub announcement_v: UintType,
+    pub announcement_c: UintType,
+    pub e: u32,
+    pub z: UintType,
+}
+
+fn factorial(k: usize) -> UintType {
+    let mut result = UintType::ONE;
+    for i in 2..=k {
+        result = result
+            .checked_mul(&UintType::from_u32(i as u32))
+            .expect("k! must not overflow in the given datatype. Increase UintType.");
+    }
+    result
+}
+
+fn monty_params(n: &UintType) -> MontyParams<{ UintType::LIMBS }> {
+    let n_squared = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width.");
+    let n_squared_odd = n_squared
+        .to_odd()
+        .expect("N^2 must be odd, because p is odd and q is odd. odd*odd->odd, odd^2=odd.");
+    MontyParams::new(n_squared_odd)
+}
+
+/// Shamir-shares `phi_n` into `k` shares with threshold `t`, following a
+/// random degree-`(t-1)` polynomial whose constant term is `phi_n`.
+pub fn split(n: &UintType, phi_n: &UintType, t: usize, k: usize) -> ThresholdSetup {
+    assert!(t >= 1 && t <= k, "Threshold t must be between 1 and k.");
+
+    let n_squared_nz = n
+        .checked_square()
+        .expect("N^2 must not overflow in the given datatype. Increase width.")
+        .to_nz()
+        .expect("N^2 must not be 0.");
+
+    // Coefficients a_1, .., a_(t-1); a_0 is phi_n itself.
+    let coefficients: Vec<UintType> = (1..t)
+        .map(|_| UintType::random_mod(&mut OsRng, &n_squared_nz))
+        .collect();
+
+    let shares: Vec<UintType> = (1..=k)
+        .map(|i| {
+            let i_uint = UintType::from_u32(i as u32);
+            let mut share = *phi_n;
+            let mut i_pow_j = UintType::ONE;
+            for a_j in &coefficients {
+                i_pow_j = i_pow_j
+                    .checked_mul(&i_uint)
+                    .expect("i^j must not overflow evaluating the sharing polynomial.");
+                let term = a_j
+                    .checked_mul(&i_pow_j)
+                    .expect("a_j * i^j must not overflow evaluating the sharing polynomial.");
+                share = share
+                    .checked_add(&term)
+                    .expect("Sharing polynomial evaluation must not overflow.");
+            }
+            share
+        })
+        .collect();
+
+    let delta = factorial(k);
+
+    let params = monty_params(n);
+    let verification_base = UintType::random_mod(&mut OsRng, &n_squared_nz);
+    let verification_keys: Vec<UintType> = shares
+        .iter()
+        .map(|lambda_i| {
+            MontyForm::new(&verification_base, params)
+                .pow(lambda_i)
+                .retrieve()
+        })
+        .collect();
+
+    // (4*delta^2*phi_n)^(-1) mod n, computed this one time while phi_n is
+    // still in hand.
+    let four_delta_squared = UintType::from_u8(4)
+        .checked_mul(&delta)
+        .and_then(|v| v.checked_mul(&delta))
+        .expect("4*delta^2 must not overflow. Increase UintType.");
+    let four_delta_squared_phi_n = four_delta_squared
+        .checked_mul(phi_n)
+        .expect("4*delta^2*phi_n must not overflow. Increase UintType.");
+    let combination_inverse = four_delta_squared_phi_n
+        .inv_mod(n)
+        .expect("4*delta^2*phi_n must have a multiplicative inverse mod n.");
+
+    ThresholdSetup {
+        t,
+        k,
+        delta,
+        shares,
+        verification_base,
+        verification_keys,
+        combination_inverse,
+    }
+}
+
+fn fiat_shamir_challenge(
+    v: &UintType,
+    v_i: &UintType,
+    c_pow_2delta: &UintType,
+    c_i: &UintType,
+    announcement_v: &UintType,
+    announcement_c: &UintType,
+) -> u32 {
+    let mut hasher = Sha256::new();
+    for value in [v, v_i, c_pow_2delta, c_i, announcement_v, announcement_c] {
+        hasher.update(value.to_string_radix_vartime(10).as_bytes());
+    }
+    let digest = hasher.finalize();
+    u32::from_be_bytes(digest[0..4].try_into().expect("sha256 digest is 32 bytes"))
+}
+
+/// Authority `authority_index` (1-indexed) computes its partial
+/// decryption of `c` and proves it used the share committed to in its
+/// verification key.
+pub fn partial_decrypt(
+    n: &UintType,
+    c: &UintType,
+    setup: &ThresholdSetup,
+    authority_index: usize,
+) -> PartialDecryption {
+    assert!(
+        (1..=setup.k).contains(&authority_index),
+        "authority_index must be a valid 1-indexed authority."
+    );
+    let lambda_i = setup.shares[authority_index - 1];
+    let v_i = setup.verification_keys[authority_index - 1];
+
+    let params = monty_params(n);
+
+    let two_delta = UintType::from_u8(2)
+        .checked_mul(&setup.delta)
+        .expect("2*delta must not overflow. Increase UintType.");
+    let c_pow_2delta = MontyForm::new(c, params).pow(&two_delta);
+    let value = c_pow_2delta.pow(&lambda_i).retrieve();
+
+    // Chaum-Pedersen: prove the same lambda_i relates (v, v_i) and
+    // (c^(2*delta), c_i). rho is NOT reduced mod n^2 like v and c_i are --
+    // see rho_bit_length.
+    let rho = UintType::random_bits(&mut OsRng, rho_bit_length(n));
+    let announcement_v = MontyForm::new(&setup.verification_base, params)
+        .pow(&rho)
+        .retrieve();
+    let announcement_c = c_pow_2delta.pow(&rho).retrieve();
+
+    let e = fiat_shamir_challenge(
+        &setup.verification_base,
+        &v_i,
+        &c_pow_2delta.retrieve(),
+        &value,
+        &announcement_v,
+        &announcement_c,
+    );
+    let e_uint = UintType::from_u32(e);
+    let z = rho
+        .checked_add(
+            &lambda_i
+                .checked_mul(&e_uint)
+                .expect("e*lambda_i must not overflow. Increase UintType."),
+        )
+        .expect("rho+e*lambda_i must not overflow. Increase UintType.");
+
+    PartialDecryption {
+        index: authority_index,
+        value,
+        proof: PartialDecryptionProof {
+            announcement_v,
+            announcement_c,
+            e,
+            z,
+        },
+    }
+}
+
+/// Verifies that `partial` is consistent with its authority's
+/// verification key, without learning `lambda_i`.
+pub fn verify_partial_decryption(n: &UintType, c: &UintType, setup: &ThresholdSetup, partial: &PartialDecryption) -> bool {
+    if !(1..=setup.k).contains(&partial.index) {
+        return false;
+    }
+    let v_i = setup.verification_keys[partial.index - 1];
+
+    let n_squared: UintType = match Option::from(n.checked_square()) {
+        Some(v) => v,
+        None => return false,
+    };
+    let n_squared_odd = match Option::from(n_squared.to_odd()) {
+        Some(v) => v,
+        None => return false,
+    };
+    let params = MontyParams::new(n_squared_odd);
+
+    let two_delta = match UintType::from_u8(2).checked_mul(&setup.delta).into_option() {
+        Some(v) => v,
+        None => return false,
+    };
+    let c_pow_2delta = MontyForm::new(c, params).pow(&two_delta);
+
+    let e = fiat_shamir_challenge(
+        &setup.verification_base,
+        &v_i,
+        &c_pow_2delta.retrieve(),
+        &partial.value,
+        &partial.proof.announcement_v,
+        &partial.proof.announcement_c,
+    );
+    if e != partial.proof.e {
+        return false;
+    }
+    let e_uint = UintType::from_u32(e);
+
+    let lhs_v = MontyForm::new(&setup.verification_base, params)
+        .pow(&partial.proof.z)
+        .retrieve();
+    let rhs_v = MontyForm::new(&partial.proof.announcement_v, params)
+        .mul(MontyForm::new(&v_i, params).pow(&e_uint))
+        .retrieve();
+    if lhs_v != rhs_v {
+        return false;
+    }
+
+    let lhs_c = c_pow_2delta.pow(&partial.proof.z).retrieve();
+    let rhs_c = MontyForm::new(&partial.proof.announcement_c, params)
+        .mul(MontyForm::new(&partial.value, params).pow(&e_uint))
+        .retrieve();
+    lhs_c == rhs_c
+}
+
+/// The public, purely combinatorial integer Lagrange coefficient
+/// `delta * prod_{j in indices, j != i} j / (j - i)`, returned as
+/// `(magnitude, is_negative)` since it can be negative. Depends only on
+/// which authority indices are present, never on any secret.
+fn lagrange_coefficient(indices: &[usize], i: usize, k: usize) -> (UintType, bool) {
+    let mut numerator: i128 = (2..=k as i128).product::<i128>().max(1);
+    let mut denominator: i128 = 1;
+    for &j in indices {
+        if j == i {
+            continue;
+        }
+        numerator *= j as i128;
+        denominator *= j as i128 - i as i128;
+    }
+    let value = numerator / denominator;
+    (UintType::from_u64(value.unsigned_abs() as u64), value < 0)
+}
+
+/// Combines at least `setup.t` valid partial decryptions into the same
+/// plaintext a single authority holding `phi_n` would have produced.
+pub fn combine(n: &UintType, setup: &ThresholdSetup, partials: &[PartialDecryption]) -> UintType {
+    assert!(
+        partials.len() >= setup.t,
+        "combine needs at least t partial decryptions."
+    );
+
+    let params = monty_params(n);
+    let indices: Vec<usize> = partials.iter().map(|p| p.index).collect();
+
+    let mut w = MontyForm::new(&UintType::ONE, params);
+    for partial in partials {
+        let (magnitude, negative) = lagrange_coefficient(&indices, partial.index, setup.k);
+        let exponent = UintType::from_u8(2)
+            .checked_mul(&magnitude)
+            .expect("2*lagrange coefficient must not overflow. Increase UintType.");
+        let c_i_pow = MontyForm::new(&partial.value, params).pow(&exponent);
+        let term = if negative {
+            c_i_pow.invert().expect("c_i must be invertible mod n^2.")
+        } else {
+            c_i_pow
+        };
+        w = w.mul(term);
+    }
+    let w = w.retrieve();
+
+    let n_nonzero = n.to_nz().expect("N must not be 0.");
+    let l_w = {
+        let w_minus_one = w
+            .checked_sub(&UintType::ONE)
+            .expect("w-1 must not underflow; w must not be 0.");
+        let (quotient, _) = w_minus_one.div_rem(&n_nonzero);
+        quotient.rem(&n_nonzero)
+    };
+
+    l_w.mul_mod(&setup.combination_inverse, &n_nonzero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::paillier::{self, Plaintext};
+
+    const P: u32 = 11;
+    const Q: u32 = 13;
+
+    fn n_and_phi_n() -> (UintType, UintType) {
+        let n = UintType::from_u32(P * Q);
+        let phi_n = UintType::from_u32((P - 1) * (Q - 1));
+        (n, phi_n)
+    }
+
+    #[test]
+    fn threshold_decryption_matches_direct_decryption() {
+        let (n, phi_n) = n_and_phi_n();
+        let m = Plaintext(UintType::from_u32(7));
+        let r = UintType::from_u32(2);
+        let c = paillier::encrypt(&n, &m, &r);
+
+        let setup = split(&n, &phi_n, 2, 3);
+        let partials: Vec<PartialDecryption> =
+            [1, 2].iter().map(|&i| partial_decrypt(&n, &c.0, &setup, i)).collect();
+        for partial in &partials {
+            assert!(verify_partial_decryption(&n, &c.0, &setup, partial));
+        }
+
+        let recovered = combine(&n, &setup, &partials);
+        assert_eq!(recovered, m.0);
+    }
+
+    #[test]
+    fn tampered_partial_rejected() {
+        let (n, phi_n) = n_and_phi_n();
+        let m = Plaintext(UintType::from_u32(7));
+        let r = UintType::from_u32(2);
+        let c = paillier::encrypt(&n, &m, &r);
+
+        let setup = split(&n, &phi_n, 2, 3);
+        let mut partial = partial_decrypt(&n, &c.0, &setup, 1);
+        partial.value = partial.value.checked_add(&UintType::ONE).expect("value+1 must not overflow.");
+        assert!(!verify_partial_decryption(&n, &c.0, &setup, &partial));
+    }
+}